@@ -0,0 +1,63 @@
+// Hegemon - A modular system monitor
+// Copyright (C) 2018-2020  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+// Streams are matched up by name rather than index, so reordering the
+// native stream list between versions doesn't scramble a user's config
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StreamConfig {
+    pub name: String,
+    pub active: bool,
+    pub expanded: bool,
+    pub width_pct: Option<u8>,
+}
+
+// Saved as TOML under the user's config directory so it survives restarts
+#[derive(Serialize, Deserialize, Default)]
+pub struct Config {
+    pub streams: Vec<StreamConfig>,
+    pub interval_index: Option<usize>,
+}
+
+impl Config {
+    // Falls back to Config::default() if missing or malformed
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("hegemon")
+            .join("config.toml")
+    }
+}
@@ -0,0 +1,198 @@
+// Hegemon - A modular system monitor
+// Copyright (C) 2018-2020  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Application;
+
+// untagged so {"subscribe": [...]} and {"list": true} parse directly,
+// instead of the externally-tagged form serde would otherwise expect
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Request {
+    Subscribe { subscribe: Vec<String> },
+    List { list: bool },
+}
+
+#[derive(Serialize)]
+struct StreamFrame<'a> {
+    stream: &'a str,
+    value: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+    unit: &'a str,
+    t: u128,
+}
+
+#[derive(Serialize)]
+struct ListFrame<'a> {
+    streams: Vec<&'a str>,
+}
+
+struct Client {
+    stream: UnixStream,
+    subscriptions: Option<HashSet<String>>,
+}
+
+// Publishes Application's stream samples to any number of Unix domain socket clients
+pub struct Daemon {
+    clients: Arc<Mutex<HashMap<u64, Client>>>,
+}
+
+impl Daemon {
+    // Binds the socket and starts accepting clients in the background.
+    // stream_names is used to answer {"list": true} requests.
+    pub fn bind(stream_names: Vec<String>) -> std::io::Result<Self> {
+        let path = socket_path()?;
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        let clients: Arc<Mutex<HashMap<u64, Client>>> = Arc::new(Mutex::new(HashMap::new()));
+        let accept_clients = Arc::clone(&clients);
+        let stream_names = Arc::new(stream_names);
+        let next_client_id = AtomicU64::new(0);
+
+        thread::spawn(move || {
+            for connection in listener.incoming().filter_map(Result::ok) {
+                let client_id = next_client_id.fetch_add(1, Ordering::Relaxed);
+                handle_client(client_id, connection, Arc::clone(&accept_clients), Arc::clone(&stream_names));
+            }
+        });
+
+        Ok(Daemon { clients })
+    }
+
+    // Sends every active stream's latest value to each connected client
+    // (only the named streams once it has subscribed), dropping clients
+    // whose connection has gone away
+    pub fn publish(&self, app: &Application) {
+        let t = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|_, client| {
+            for stream in app.active_streams() {
+                let name = stream.stream.name();
+                if let Some(subscriptions) = &client.subscriptions {
+                    if !subscriptions.contains(&name) {
+                        continue;
+                    }
+                }
+
+                let frame = StreamFrame {
+                    stream: &name,
+                    value: stream.values.back().copied().flatten(),
+                    min: stream.stream.min(),
+                    max: stream.stream.max(),
+                    unit: &stream.stream.unit(),
+                    t,
+                };
+
+                if !send_line(&mut client.stream, &frame) {
+                    return false;
+                }
+            }
+            true
+        });
+    }
+}
+
+fn handle_client(
+    client_id: u64,
+    stream: UnixStream,
+    clients: Arc<Mutex<HashMap<u64, Client>>>,
+    stream_names: Arc<Vec<String>>,
+) {
+    let mut reader_stream = match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    };
+
+    {
+        let mut clients = clients.lock().unwrap();
+        clients.insert(
+            client_id,
+            Client {
+                stream,
+                subscriptions: None,
+            },
+        );
+    }
+
+    thread::spawn(move || {
+        let request_stream = match reader_stream.try_clone() {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+
+        for line in BufReader::new(request_stream).lines().filter_map(Result::ok) {
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+
+            match request {
+                Request::Subscribe { subscribe } => {
+                    let mut clients = clients.lock().unwrap();
+                    if let Some(client) = clients.get_mut(&client_id) {
+                        client.subscriptions = Some(subscribe.into_iter().collect());
+                    }
+                }
+                Request::List { .. } => {
+                    let names: Vec<&str> = stream_names.iter().map(String::as_str).collect();
+                    let _ = send_line(&mut reader_stream, &ListFrame { streams: names });
+                }
+            }
+        }
+    });
+}
+
+fn send_line<T: Serialize>(stream: &mut UnixStream, value: &T) -> bool {
+    let mut line = match serde_json::to_string(value) {
+        Ok(line) => line,
+        Err(_) => return false,
+    };
+    line.push('\n');
+    stream.write_all(line.as_bytes()).is_ok()
+}
+
+// Falls back to a cache-dir subdirectory we chmod 0700 ourselves, never a
+// shared location like /tmp, if XDG_RUNTIME_DIR isn't set
+fn socket_path() -> io::Result<PathBuf> {
+    if let Some(runtime_dir) = dirs::runtime_dir() {
+        return Ok(runtime_dir.join("hegemon.sock"));
+    }
+
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no runtime or cache directory available"))?
+        .join("hegemon");
+    fs::create_dir_all(&dir)?;
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    Ok(dir.join("hegemon.sock"))
+}
@@ -0,0 +1,105 @@
+// Hegemon - A modular system monitor
+// Copyright (C) 2018-2020  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use termion::event::Key;
+
+use crate::model::{Mode, Screen};
+
+/// A high-level command that a key can be bound to, independent of any
+/// particular screen or mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    SelectUp,
+    SelectDown,
+    SelectColumnLeft,
+    SelectColumnRight,
+    ToggleExpand,
+    OpenStreams,
+    CloseStreams,
+    IntervalUp,
+    IntervalDown,
+    ToggleActive,
+    ReorderUp,
+    ReorderDown,
+    EnterMotion,
+    ExitMotion,
+    GoTop,
+    GoBottom,
+    ToggleLayout,
+    TogglePalette,
+    Quit,
+}
+
+/// Maps a `(Screen, Mode)` pair to the keys that are bound in it, so the
+/// same physical key can mean different things depending on where the user
+/// currently is (e.g. `Space` toggles expansion on the main screen, but
+/// toggles activation on the streams screen) and whether motion mode is
+/// active.
+pub type Keymap = HashMap<(Screen, Mode), HashMap<Key, Action>>;
+
+/// Builds the keymap matching Hegemon's historical, hardcoded bindings,
+/// plus a separate set of vi-style motion mode bindings that replaces it
+/// while motion mode is active.
+pub fn default_keymap() -> Keymap {
+    let mut keymap = Keymap::new();
+
+    let mut main_normal = HashMap::new();
+    main_normal.insert(Key::Up, Action::SelectUp);
+    main_normal.insert(Key::Down, Action::SelectDown);
+    main_normal.insert(Key::Left, Action::SelectColumnLeft);
+    main_normal.insert(Key::Right, Action::SelectColumnRight);
+    main_normal.insert(Key::Char(' '), Action::ToggleExpand);
+    main_normal.insert(Key::Char('s'), Action::OpenStreams);
+    main_normal.insert(Key::Char('+'), Action::IntervalUp);
+    main_normal.insert(Key::Char('-'), Action::IntervalDown);
+    main_normal.insert(Key::Char('q'), Action::Quit);
+    main_normal.insert(Key::Char('v'), Action::EnterMotion);
+    main_normal.insert(Key::Char('l'), Action::ToggleLayout);
+    main_normal.insert(Key::Char('p'), Action::TogglePalette);
+    keymap.insert((Screen::Main, Mode::Normal), main_normal);
+
+    let mut main_motion = HashMap::new();
+    main_motion.insert(Key::Char('j'), Action::SelectDown);
+    main_motion.insert(Key::Char('k'), Action::SelectUp);
+    main_motion.insert(Key::Char('h'), Action::SelectColumnLeft);
+    main_motion.insert(Key::Char('l'), Action::SelectColumnRight);
+    main_motion.insert(Key::Char('g'), Action::GoTop);
+    main_motion.insert(Key::Char('G'), Action::GoBottom);
+    main_motion.insert(Key::Esc, Action::ExitMotion);
+    keymap.insert((Screen::Main, Mode::Motion), main_motion);
+
+    let mut streams_normal = HashMap::new();
+    streams_normal.insert(Key::Up, Action::SelectUp);
+    streams_normal.insert(Key::Down, Action::SelectDown);
+    streams_normal.insert(Key::Char(' '), Action::ToggleActive);
+    streams_normal.insert(Key::Char('+'), Action::ReorderUp);
+    streams_normal.insert(Key::Char('-'), Action::ReorderDown);
+    streams_normal.insert(Key::Esc, Action::CloseStreams);
+    streams_normal.insert(Key::Char('v'), Action::EnterMotion);
+    keymap.insert((Screen::Streams, Mode::Normal), streams_normal);
+
+    let mut streams_motion = HashMap::new();
+    streams_motion.insert(Key::Char('j'), Action::SelectDown);
+    streams_motion.insert(Key::Char('k'), Action::SelectUp);
+    streams_motion.insert(Key::Char('g'), Action::GoTop);
+    streams_motion.insert(Key::Char('G'), Action::GoBottom);
+    streams_motion.insert(Key::Esc, Action::ExitMotion);
+    keymap.insert((Screen::Streams, Mode::Motion), streams_motion);
+
+    keymap
+}
@@ -19,6 +19,10 @@ use std::time::Duration;
 
 use termion::event::{Event, Key, MouseButton, MouseEvent};
 
+use crate::config::{Config, StreamConfig};
+use crate::daemon::Daemon;
+use crate::keymap::{default_keymap, Action, Keymap};
+use crate::palette::{self, Palette};
 use crate::stream::Stream;
 
 const VALUE_HISTORY_SIZE: usize = 512;
@@ -28,29 +32,57 @@ pub struct Application {
     pub width: usize,
     pub height: usize,
     pub screen: Screen,
+    pub mode: Mode,
+    pub layout: Layout,
+    pub palette: Palette,
     pub streams: Vec<StreamWrapper>,
+    pub selected_column: usize,
     pub selection_index: usize,
-    pub scroll_index: usize,
-    pub scroll_anchor: ScrollAnchor,
+    // Selection cursor for the Streams screen, which ranges over every
+    // stream (including inactive ones), unlike `selection_index` which
+    // ranges over `active_streams()`
+    pub streams_selection_index: usize,
+    // One scroll cursor per column; in `Layout::Stacked` this always holds
+    // exactly one entry
+    columns: Vec<ColumnState>,
     intervals: Vec<Interval>,
     pub interval_index: usize,
     // The two parts of the map value contain
     // the left/right-aligned menu items, respectively
     menus: HashMap<Screen, (Vec<MenuItem>, Vec<MenuItem>)>,
+    keymap: Keymap,
+    // Numeric prefix accumulated in motion mode (e.g. the "5" in "5j"),
+    // cleared whenever a motion is resolved and applied
+    count_buffer: String,
+    // `Some` once `enable_daemon` has bound the socket; every `update_streams`
+    // tick publishes the fresh samples to it
+    daemon: Option<Daemon>,
 }
 
 impl Application {
-    pub fn new(width: usize, height: usize, streams: Vec<Box<dyn Stream>>) -> Self {
+    pub fn new(width: usize, height: usize, mut streams: Vec<Box<dyn Stream>>) -> Self {
+        // Plugins loaded from the user's plugins directory flow through
+        // `update_streams` exactly like natively compiled streams
+        if let Some(plugins_dir) = dirs::data_dir().map(|dir| dir.join("hegemon/plugins")) {
+            streams.extend(crate::wasm_stream::load_plugins(&plugins_dir));
+        }
+
+        let config = Config::load();
+        let mut streams: Vec<StreamWrapper> = streams.into_iter().map(StreamWrapper::new).collect();
+        apply_stream_config(&mut streams, &config.streams);
+
         let mut menus = HashMap::new();
 
         menus.insert(
             Screen::Main,
             (
                 vec![
-                    MenuItem::new("\u{1F805}\u{1F807}", "Select"),
+                    MenuItem::new("\u{1F805}\u{1F806}\u{1F807}\u{1F808}", "Select"),
                     MenuItem::new("Space", "Expand"),
                     MenuItem::new("S", "Streams"),
                     MenuItem::new("+-", "Interval"),
+                    MenuItem::new("L", "Layout"),
+                    MenuItem::new("P", "Palette"),
                 ],
                 vec![MenuItem::new("Q", "Quit")],
             ),
@@ -68,33 +100,50 @@ impl Application {
             ),
         );
 
+        let intervals = vec![
+            Interval::new(100, 10),
+            Interval::new(200, 10),
+            Interval::new(500, 10),
+            Interval::new(1_000, 10),
+            Interval::new(2_000, 15),
+            Interval::new(3_000, 10),
+            Interval::new(5_000, 12),
+            Interval::new(10_000, 12),
+            Interval::new(30_000, 10),
+            Interval::new(60_000, 10),
+            Interval::new(300_000, 12),
+        ];
+        let interval_index = config.interval_index.unwrap_or(3).min(intervals.len() - 1);
+
         Application {
             running: true,
             width,
             height,
             screen: Screen::Main,
-            streams: streams.into_iter().map(StreamWrapper::new).collect(),
+            mode: Mode::Normal,
+            layout: Layout::Stacked,
+            palette: palette::detect(),
+            streams,
+            selected_column: 0,
             selection_index: 0,
-            scroll_index: 0,
-            scroll_anchor: ScrollAnchor::Top,
-            intervals: vec![
-                Interval::new(100, 10),
-                Interval::new(200, 10),
-                Interval::new(500, 10),
-                Interval::new(1_000, 10),
-                Interval::new(2_000, 15),
-                Interval::new(3_000, 10),
-                Interval::new(5_000, 12),
-                Interval::new(10_000, 12),
-                Interval::new(30_000, 10),
-                Interval::new(60_000, 10),
-                Interval::new(300_000, 12),
-            ],
-            interval_index: 3,
+            streams_selection_index: 0,
+            columns: vec![ColumnState::default()],
+            intervals,
+            interval_index,
             menus,
+            keymap: default_keymap(),
+            count_buffer: String::new(),
+            daemon: None,
         }
     }
 
+    // Binds the daemon's socket; called once at startup when the user passes the daemon flag
+    pub fn enable_daemon(&mut self) -> std::io::Result<()> {
+        let stream_names = self.streams.iter().map(|s| s.stream.name()).collect();
+        self.daemon = Some(Daemon::bind(stream_names)?);
+        Ok(())
+    }
+
     pub fn interval(&self) -> Interval {
         self.intervals[self.interval_index]
     }
@@ -107,89 +156,262 @@ impl Application {
         self.streams.iter().filter(|s| s.active).collect()
     }
 
-    pub fn handle(&mut self, event: &Event) -> bool {
-        match self.screen {
-            Screen::Main => match event {
-                Event::Key(key) => match key {
-                    Key::Up => {
-                        if self.selection_index > 0 {
-                            self.selection_index -= 1;
-                            self.scroll_to_stream(self.selection_index);
-                            return true;
-                        }
-                    }
-                    Key::Down => {
-                        if self.selection_index < self.active_streams().len() - 1 {
-                            self.selection_index += 1;
-                            self.scroll_to_stream(self.selection_index);
-                            return true;
-                        }
-                    }
-                    Key::Char(' ') => {
-                        let stream = self
-                            .streams
-                            .iter_mut()
-                            .filter(|s| s.active)
-                            .nth(self.selection_index)
-                            .unwrap();
-                        stream.expanded = !stream.expanded;
-                        self.scroll_to_stream(self.selection_index);
-                        return true;
+    /// Partitions the active streams' indices (into `active_streams()`)
+    /// into columns. In `Layout::Stacked` there is always exactly one
+    /// column holding every active stream; in `Layout::Grid`, streams are
+    /// packed greedily by `width_pct` (defaulting to a full-width 100),
+    /// wrapping into a new column whenever the current one would exceed
+    /// 100%.
+    fn column_indices(&self) -> Vec<Vec<usize>> {
+        let active = self.active_streams();
+
+        match self.layout {
+            Layout::Stacked => vec![(0..active.len()).collect()],
+            Layout::Grid => {
+                let mut columns: Vec<Vec<usize>> = vec![Vec::new()];
+                let mut column_pct: u32 = 0;
+
+                for (index, stream) in active.iter().enumerate() {
+                    let pct = u32::from(stream.width_pct.unwrap_or(100));
+                    if column_pct + pct > 100 && !columns.last().unwrap().is_empty() {
+                        columns.push(Vec::new());
+                        column_pct = 0;
                     }
-                    Key::Char('s') => {
-                        self.screen = Screen::Streams;
-                        return true;
-                    }
-                    Key::Char('+') => {
-                        if self.interval_index < self.intervals.len() - 1 {
-                            self.interval_index += 1;
-                            return true;
-                        }
-                    }
-                    Key::Char('-') => {
-                        if self.interval_index > 0 {
-                            self.interval_index -= 1;
-                            return true;
-                        }
-                    }
-                    Key::Char('q') => {
-                        self.running = false;
-                        return true;
-                    }
-                    _ => {}
-                },
-                Event::Mouse(MouseEvent::Press(mouse_button, _, _)) => match mouse_button {
-                    MouseButton::WheelUp => {
-                        return self.handle(&Event::Key(Key::Down));
-                    }
-                    MouseButton::WheelDown => {
-                        return self.handle(&Event::Key(Key::Up));
-                    }
-                    _ => {}
-                },
-                _ => {}
-            },
-
-            Screen::Streams => match event {
-                Event::Key(key) => match key {
-                    Key::Up => {}
-                    Key::Down => {}
-                    Key::Char(' ') => {}
-                    Key::Char('+') => {}
-                    Key::Char('-') => {}
-                    Key::Esc => {
-                        self.screen = Screen::Main;
-                        return true;
-                    }
-                    _ => {}
-                },
-                Event::Mouse(MouseEvent::Press(mouse_button, _, _)) => match mouse_button {
-                    MouseButton::WheelUp => {}
-                    MouseButton::WheelDown => {}
-                    _ => {}
-                },
-                _ => {}
-            },
+                    columns.last_mut().unwrap().push(index);
+                    column_pct += pct;
+                }
+
+                columns
+            }
+        }
+    }
+
+    fn column_streams(&self, column: usize) -> Vec<&StreamWrapper> {
+        let active = self.active_streams();
+        self.column_indices()
+            .get(column)
+            .map(|indices| indices.iter().map(|&i| active[i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Keeps `self.columns` (one scroll cursor per column) and
+    /// `selected_column` in sync with the current layout and active
+    /// stream set, which can change independently of either.
+    fn sync_columns(&mut self) {
+        let column_count = self.column_indices().len().max(1);
+
+        if self.columns.len() != column_count {
+            self.columns.resize_with(column_count, ColumnState::default);
+        }
+
+        if self.selected_column >= column_count {
+            self.selected_column = column_count - 1;
+        }
+    }
+
+    /// Re-clamps the main screen's selection to the current active stream
+    /// set. Toggling a stream's activation (or leaving the Streams screen
+    /// after doing so) can shrink `active_streams()` out from under
+    /// `selection_index`/`selected_column`; without this, the main screen
+    /// cursor can point past the end of the active set until the next
+    /// Select keypress happens to fix it.
+    fn clamp_main_selection(&mut self) {
+        self.sync_columns();
+        let max_index = self.column_streams(self.selected_column).len().saturating_sub(1);
+        self.selection_index = self.selection_index.min(max_index);
+        self.scroll_to_stream(self.selected_column, self.selection_index);
+    }
+
+    pub fn handle(&mut self, event: &Event) -> bool {
+        // Mouse wheel events are translated into the key events they have
+        // always been equivalent to, and resolved from there
+        if let Event::Mouse(MouseEvent::Press(mouse_button, _, _)) = event {
+            return match mouse_button {
+                MouseButton::WheelUp => self.handle(&Event::Key(Key::Down)),
+                MouseButton::WheelDown => self.handle(&Event::Key(Key::Up)),
+                _ => false,
+            };
+        }
+
+        let key = match event {
+            Event::Key(key) => key,
+            _ => return false,
+        };
+
+        // In motion mode, digits accumulate into a repetition count instead
+        // of being dispatched, so e.g. "5j" is resolved as SelectDown * 5
+        if self.mode == Mode::Motion {
+            if let Key::Char(digit @ '0'..='9') = key {
+                if !(*digit == '0' && self.count_buffer.is_empty()) {
+                    self.count_buffer.push(*digit);
+                    return true;
+                }
+            }
+        }
+
+        let action = match self.keymap.get(&(self.screen, self.mode)).and_then(|m| m.get(key)) {
+            Some(action) => *action,
+            None => {
+                self.count_buffer.clear();
+                return false;
+            }
+        };
+
+        let count: usize = self.count_buffer.parse().unwrap_or(1).max(1);
+        self.count_buffer.clear();
+
+        self.sync_columns();
+        self.dispatch(action, count)
+    }
+
+    fn dispatch(&mut self, action: Action, count: usize) -> bool {
+        match action {
+            Action::SelectUp if self.screen == Screen::Streams => {
+                let new_index = self.streams_selection_index.saturating_sub(count);
+                if new_index != self.streams_selection_index {
+                    self.streams_selection_index = new_index;
+                    return true;
+                }
+            }
+            Action::SelectDown if self.screen == Screen::Streams => {
+                let max_index = self.streams.len().saturating_sub(1);
+                let new_index = (self.streams_selection_index + count).min(max_index);
+                if new_index != self.streams_selection_index {
+                    self.streams_selection_index = new_index;
+                    return true;
+                }
+            }
+            Action::SelectUp => {
+                let new_index = self.selection_index.saturating_sub(count);
+                if new_index != self.selection_index {
+                    self.selection_index = new_index;
+                    self.scroll_to_stream(self.selected_column, self.selection_index);
+                    return true;
+                }
+            }
+            Action::SelectDown => {
+                let max_index = self.column_streams(self.selected_column).len().saturating_sub(1);
+                let new_index = (self.selection_index + count).min(max_index);
+                if new_index != self.selection_index {
+                    self.selection_index = new_index;
+                    self.scroll_to_stream(self.selected_column, self.selection_index);
+                    return true;
+                }
+            }
+            Action::SelectColumnLeft => {
+                if self.selected_column > 0 {
+                    self.selected_column -= 1;
+                    let max_index = self.column_streams(self.selected_column).len().saturating_sub(1);
+                    self.selection_index = self.selection_index.min(max_index);
+                    self.scroll_to_stream(self.selected_column, self.selection_index);
+                    return true;
+                }
+            }
+            Action::SelectColumnRight => {
+                if self.selected_column + 1 < self.columns.len() {
+                    self.selected_column += 1;
+                    let max_index = self.column_streams(self.selected_column).len().saturating_sub(1);
+                    self.selection_index = self.selection_index.min(max_index);
+                    self.scroll_to_stream(self.selected_column, self.selection_index);
+                    return true;
+                }
+            }
+            Action::GoTop if self.screen == Screen::Streams => {
+                self.streams_selection_index = 0;
+                return true;
+            }
+            Action::GoBottom if self.screen == Screen::Streams => {
+                self.streams_selection_index = self.streams.len().saturating_sub(1);
+                return true;
+            }
+            Action::GoTop => {
+                self.selection_index = 0;
+                self.scroll_to_stream(self.selected_column, 0);
+                return true;
+            }
+            Action::GoBottom => {
+                self.selection_index = self.column_streams(self.selected_column).len().saturating_sub(1);
+                self.scroll_to_stream(self.selected_column, self.selection_index);
+                return true;
+            }
+            Action::ToggleExpand => {
+                if let Some(global_index) = self.global_active_index(self.selected_column, self.selection_index) {
+                    let stream = self.streams.iter_mut().filter(|s| s.active).nth(global_index).unwrap();
+                    stream.expanded = !stream.expanded;
+                    self.scroll_to_stream(self.selected_column, self.selection_index);
+                    return true;
+                }
+            }
+            Action::OpenStreams => {
+                self.screen = Screen::Streams;
+                return true;
+            }
+            Action::CloseStreams => {
+                self.screen = Screen::Main;
+                self.clamp_main_selection();
+                self.save_config();
+                return true;
+            }
+            Action::IntervalUp => {
+                if self.interval_index < self.intervals.len() - 1 {
+                    self.interval_index += 1;
+                    return true;
+                }
+            }
+            Action::IntervalDown => {
+                if self.interval_index > 0 {
+                    self.interval_index -= 1;
+                    return true;
+                }
+            }
+            Action::ToggleActive => {
+                if !self.streams.is_empty() {
+                    self.streams[self.streams_selection_index].active = !self.streams[self.streams_selection_index].active;
+                    self.clamp_main_selection();
+                    return true;
+                }
+            }
+            Action::ReorderUp => {
+                if self.streams_selection_index > 0 {
+                    self.streams.swap(self.streams_selection_index, self.streams_selection_index - 1);
+                    self.streams_selection_index -= 1;
+                    return true;
+                }
+            }
+            Action::ReorderDown => {
+                if self.streams_selection_index + 1 < self.streams.len() {
+                    self.streams.swap(self.streams_selection_index, self.streams_selection_index + 1);
+                    self.streams_selection_index += 1;
+                    return true;
+                }
+            }
+            Action::EnterMotion => {
+                self.mode = Mode::Motion;
+                return true;
+            }
+            Action::ExitMotion => {
+                self.mode = Mode::Normal;
+                return true;
+            }
+            Action::ToggleLayout => {
+                self.layout = match self.layout {
+                    Layout::Stacked => Layout::Grid,
+                    Layout::Grid => Layout::Stacked,
+                };
+                self.selected_column = 0;
+                self.selection_index = 0;
+                self.sync_columns();
+                return true;
+            }
+            Action::TogglePalette => {
+                self.palette = self.palette.toggled();
+                return true;
+            }
+            Action::Quit => {
+                self.running = false;
+                self.save_config();
+                return true;
+            }
         }
 
         false
@@ -200,18 +422,30 @@ impl Application {
         self.height = height;
     }
 
-    fn scroll_to_stream(&mut self, index: usize) {
-        let active_streams = self.active_streams();
+    /// Maps a local index within `column` (as returned by
+    /// `column_streams`) back to its index into `active_streams()`.
+    fn global_active_index(&self, column: usize, local_index: usize) -> Option<usize> {
+        self.column_indices().get(column).and_then(|indices| indices.get(local_index).copied())
+    }
+
+    fn scroll_to_stream(&mut self, column: usize, index: usize) {
+        let scroll_index = self.columns[column].scroll_index;
+        let scroll_anchor = self.columns[column].scroll_anchor;
 
-        let streams = match self.scroll_anchor {
-            ScrollAnchor::Top => active_streams[self.scroll_index..].iter().collect::<Vec<_>>(),
-            ScrollAnchor::Bottom => active_streams[..=self.scroll_index].iter().rev().collect::<Vec<_>>(),
+        let streams = self.column_streams(column);
+        if streams.is_empty() {
+            return;
+        }
+
+        let ordered = match scroll_anchor {
+            ScrollAnchor::Top => streams[scroll_index..].to_vec(),
+            ScrollAnchor::Bottom => streams[..=scroll_index].iter().rev().copied().collect::<Vec<_>>(),
         };
 
         let mut stream_count = 0;
         let mut available_height = self.height - 2;
 
-        for stream in streams {
+        for stream in ordered {
             let height = stream.height();
             if height > available_height {
                 break;
@@ -226,17 +460,18 @@ impl Application {
         }
 
         // Indices of the first and last streams that are *completely* visible
-        let (top_index, bottom_index) = match self.scroll_anchor {
-            ScrollAnchor::Top => (self.scroll_index, self.scroll_index + stream_count),
-            ScrollAnchor::Bottom => (self.scroll_index - stream_count, self.scroll_index),
+        let (top_index, bottom_index) = match scroll_anchor {
+            ScrollAnchor::Top => (scroll_index, scroll_index + stream_count),
+            ScrollAnchor::Bottom => (scroll_index - stream_count, scroll_index),
         };
 
+        let state = &mut self.columns[column];
         if index < top_index {
-            self.scroll_index = index;
-            self.scroll_anchor = ScrollAnchor::Top;
+            state.scroll_index = index;
+            state.scroll_anchor = ScrollAnchor::Top;
         } else if index > bottom_index {
-            self.scroll_index = index;
-            self.scroll_anchor = ScrollAnchor::Bottom;
+            state.scroll_index = index;
+            state.scroll_anchor = ScrollAnchor::Bottom;
         }
     }
 
@@ -262,6 +497,11 @@ impl Application {
                 }
             }
         }
+
+        if let Some(daemon) = self.daemon.take() {
+            daemon.publish(self);
+            self.daemon = Some(daemon);
+        }
     }
 
     pub fn reset_streams(&mut self) {
@@ -270,19 +510,90 @@ impl Application {
             stream.values.clear();
         }
     }
+
+    /// Persists the current stream order/visibility/expansion and sampling
+    /// interval so they survive a restart.
+    pub fn save_config(&self) {
+        let config = Config {
+            streams: self
+                .streams
+                .iter()
+                .map(|s| StreamConfig {
+                    name: s.stream.name(),
+                    active: s.active,
+                    expanded: s.expanded,
+                    width_pct: s.width_pct,
+                })
+                .collect(),
+            interval_index: Some(self.interval_index),
+        };
+
+        if let Err(error) = config.save() {
+            eprintln!("Failed to save configuration: {}", error);
+        }
+    }
+}
+
+// Reorders streams to match config (by name); streams config doesn't mention
+// keep their default state and are appended, preserving relative order
+fn apply_stream_config(streams: &mut Vec<StreamWrapper>, config: &[StreamConfig]) {
+    let mut reordered = Vec::with_capacity(streams.len());
+
+    for entry in config {
+        // `position` only ever matches the first remaining stream with this
+        // name, so if two streams legitimately share a name (e.g. a plugin
+        // named the same as a native stream) neither is dropped: the first
+        // is reordered here and the rest fall through to the "leftover"
+        // extend below, in their original order.
+        match streams.iter().position(|s| s.stream.name() == entry.name) {
+            Some(pos) => {
+                let mut stream = streams.remove(pos);
+                stream.active = entry.active;
+                stream.expanded = entry.expanded;
+                stream.width_pct = entry.width_pct;
+                reordered.push(stream);
+            }
+            None => {
+                eprintln!(
+                    "Stream config entry '{}' has no matching stream (duplicate entry or stream no longer exists); ignoring",
+                    entry.name
+                );
+            }
+        }
+    }
+
+    // Anything left in `streams` wasn't in the config (e.g. a newly added
+    // stream, or the config predates this stream existing); keep it, in its
+    // original relative order.
+    reordered.append(streams);
+    *streams = reordered;
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Screen {
     Main,
     Streams,
 }
 
+/// The current input mode. `Normal` preserves Hegemon's historical,
+/// menu-driven bindings; `Motion` replaces them with vi-style movement
+/// (`j`/`k`/`g`/`G`, optionally prefixed with a repetition count), and only
+/// those keys and `Esc` (back to `Normal`) are bound while it is active.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Normal,
+    Motion,
+}
+
 pub struct StreamWrapper {
     pub stream: Box<dyn Stream>,
     pub values: VecDeque<Option<f64>>,
     pub active: bool,
     pub expanded: bool,
+    // Requested width as a percentage of the terminal width, used to pack
+    // streams into columns under `Layout::Grid`. `None` means "full width",
+    // which is also what every stream behaves as under `Layout::Stacked`.
+    pub width_pct: Option<u8>,
 }
 
 impl StreamWrapper {
@@ -292,16 +603,37 @@ impl StreamWrapper {
             values: VecDeque::new(),
             active: true,
             expanded: false,
+            width_pct: None,
         }
     }
 }
 
-#[derive(PartialEq, Eq)]
+/// Whether streams are rendered as a single vertical stack or packed into
+/// multiple columns (see `Application::column_indices`).
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Layout {
+    Stacked,
+    Grid,
+}
+
+#[derive(Default)]
+struct ColumnState {
+    scroll_index: usize,
+    scroll_anchor: ScrollAnchor,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum ScrollAnchor {
     Top,
     Bottom,
 }
 
+impl Default for ScrollAnchor {
+    fn default() -> Self {
+        ScrollAnchor::Top
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Interval {
     pub duration: Duration,
@@ -331,3 +663,98 @@ impl MenuItem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockStream(&'static str);
+
+    impl Stream for MockStream {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn unit(&self) -> String {
+            String::new()
+        }
+
+        fn min(&self) -> Option<f64> {
+            None
+        }
+
+        fn max(&self) -> Option<f64> {
+            None
+        }
+
+        fn value(&mut self) -> Option<f64> {
+            None
+        }
+    }
+
+    fn wrapper(name: &'static str) -> StreamWrapper {
+        StreamWrapper::new(Box::new(MockStream(name)))
+    }
+
+    fn config_entry(name: &str, width_pct: Option<u8>) -> StreamConfig {
+        StreamConfig {
+            name: name.to_string(),
+            active: false,
+            expanded: true,
+            width_pct,
+        }
+    }
+
+    fn names(streams: &[StreamWrapper]) -> Vec<String> {
+        streams.iter().map(|s| s.stream.name()).collect()
+    }
+
+    #[test]
+    fn apply_stream_config_reorders_and_applies_fields() {
+        let mut streams = vec![wrapper("cpu"), wrapper("mem"), wrapper("disk")];
+        let config = vec![config_entry("disk", Some(50)), config_entry("cpu", None)];
+
+        apply_stream_config(&mut streams, &config);
+
+        // Config order first, then leftovers in their original order
+        assert_eq!(names(&streams), vec!["disk", "cpu", "mem"]);
+        assert_eq!(streams[0].width_pct, Some(50));
+        assert!(!streams[0].active);
+        assert!(streams[0].expanded);
+    }
+
+    #[test]
+    fn apply_stream_config_is_deterministic_on_repeated_runs() {
+        // A config-less (e.g. fresh-install) run must keep streams in their
+        // original order every time, never shuffle them between runs.
+        for _ in 0..20 {
+            let mut streams = vec![wrapper("cpu"), wrapper("mem"), wrapper("disk"), wrapper("net")];
+            apply_stream_config(&mut streams, &[]);
+            assert_eq!(names(&streams), vec!["cpu", "mem", "disk", "net"]);
+        }
+    }
+
+    #[test]
+    fn apply_stream_config_keeps_duplicate_names_instead_of_dropping_one() {
+        let mut streams = vec![wrapper("temp"), wrapper("temp")];
+        let config = vec![config_entry("temp", Some(25))];
+
+        apply_stream_config(&mut streams, &config);
+
+        // Neither stream is lost: the first match is reordered/updated, the
+        // second is kept as a leftover.
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].width_pct, Some(25));
+        assert_eq!(streams[1].width_pct, None);
+    }
+
+    #[test]
+    fn apply_stream_config_ignores_entries_with_no_matching_stream() {
+        let mut streams = vec![wrapper("cpu")];
+        let config = vec![config_entry("gpu", Some(10))];
+
+        apply_stream_config(&mut streams, &config);
+
+        assert_eq!(names(&streams), vec!["cpu"]);
+    }
+}
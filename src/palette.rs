@@ -0,0 +1,165 @@
+// Hegemon - A modular system monitor
+// Copyright (C) 2018-2020  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+// The set of colors rendering draws from, so the UI adapts to the terminal's background
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub background: Rgb,
+    pub foreground: Rgb,
+    pub selection: Rgb,
+    pub graph: Rgb,
+    pub menu_bar: Rgb,
+}
+
+pub const DARK: Palette = Palette {
+    name: "dark",
+    background: Rgb(0x00, 0x00, 0x00),
+    foreground: Rgb(0xd8, 0xd8, 0xd8),
+    selection: Rgb(0x44, 0x44, 0x44),
+    graph: Rgb(0x00, 0xaf, 0xaf),
+    menu_bar: Rgb(0x26, 0x26, 0x26),
+};
+
+pub const LIGHT: Palette = Palette {
+    name: "light",
+    background: Rgb(0xff, 0xff, 0xff),
+    foreground: Rgb(0x26, 0x26, 0x26),
+    selection: Rgb(0xd8, 0xd8, 0xd8),
+    graph: Rgb(0x00, 0x5f, 0x5f),
+    menu_bar: Rgb(0xe4, 0xe4, 0xe4),
+};
+
+impl Palette {
+    pub fn toggled(self) -> Palette {
+        if self.name == DARK.name {
+            LIGHT
+        } else {
+            DARK
+        }
+    }
+}
+
+// Asks the terminal for its background color via OSC 11 and picks a matching
+// palette, falling back to DARK if it doesn't answer within the timeout.
+// Must be called while the terminal is in raw mode.
+pub fn detect() -> Palette {
+    match query_background_luminance() {
+        Some(luminance) if luminance > 0.5 => LIGHT,
+        _ => DARK,
+    }
+}
+
+// Polls stdin with a timeout before reading, so a timeout consumes nothing
+// and the main input loop still sees the user's next keystroke
+fn query_background_luminance() -> Option<f64> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(b"\x1b]11;?\x07").ok()?;
+    stdout.flush().ok()?;
+
+    let stdin = std::io::stdin();
+    let mut poll_fd = libc::pollfd {
+        fd: stdin.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = i32::try_from(BACKGROUND_QUERY_TIMEOUT.as_millis()).unwrap_or(i32::MAX);
+
+    // SAFETY: `poll_fd` is a single, live `pollfd` and `nfds` matches it
+    let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+    if ready <= 0 || poll_fd.revents & libc::POLLIN == 0 {
+        // Timed out (or errored) before the terminal replied; no bytes
+        // were consumed, so the main input loop sees everything the user
+        // types afterwards
+        return None;
+    }
+
+    let mut buf = [0u8; 32];
+    let n = stdin.lock().read(&mut buf).ok()?;
+    parse_osc_11_luminance(&String::from_utf8_lossy(&buf[..n]))
+}
+
+// Parses a `\x1b]11;rgb:RRRR/GGGG/BBBB\x07`-style reply into relative luminance
+fn parse_osc_11_luminance(reply: &str) -> Option<f64> {
+    let body = &reply[reply.find("rgb:")? + 4..];
+    let mut channels = body.splitn(3, '/');
+
+    let r = parse_channel(channels.next()?)?;
+    let g = parse_channel(channels.next()?)?;
+    let b = parse_channel(channels.next()?)?;
+
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b)
+}
+
+// Real OSC 11 replies encode each channel as exactly 4 hex digits
+// (`rgb:RRRR/GGGG/BBBB`). Truncating to that width keeps `1 << (hex.len() *
+// 4)` below, safely under `1 << 32`: without it, a reply with a longer hex
+// run (e.g. one padded with extra leading zeros) would overflow the shift
+// and panic, taking the whole process down during startup.
+const CHANNEL_HEX_DIGITS: usize = 4;
+
+fn parse_channel(channel: &str) -> Option<f64> {
+    let hex: String = channel
+        .chars()
+        .take_while(|c| c.is_ascii_hexdigit())
+        .take(CHANNEL_HEX_DIGITS)
+        .collect();
+    if hex.is_empty() {
+        return None;
+    }
+    let value = u32::from_str_radix(&hex, 16).ok()?;
+    let max = (1u32 << (hex.len() * 4)) - 1;
+    Some(f64::from(value) / f64::from(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_channel_reads_a_typical_four_digit_value() {
+        assert_eq!(parse_channel("ffff"), Some(1.0));
+        assert_eq!(parse_channel("0000"), Some(0.0));
+    }
+
+    #[test]
+    fn parse_channel_stops_at_the_first_non_hex_character() {
+        assert_eq!(parse_channel("00ff/"), Some(255.0 / 65535.0));
+    }
+
+    #[test]
+    fn parse_channel_rejects_empty_input() {
+        assert_eq!(parse_channel(""), None);
+        assert_eq!(parse_channel("/"), None);
+    }
+
+    #[test]
+    fn parse_channel_does_not_panic_on_an_oversized_hex_run() {
+        // A reply with more than 4 hex digits (e.g. zero-padded) must not
+        // overflow the `1 << (hex.len() * 4)` shift.
+        assert_eq!(parse_channel("000000001"), Some(0.0));
+        assert_eq!(parse_channel("ffffffff"), Some(1.0));
+    }
+}
@@ -0,0 +1,314 @@
+// Hegemon - A modular system monitor
+// Copyright (C) 2018-2020  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::Path;
+
+use wasmtime::{Caller, Config, Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::stream::Stream;
+
+// Refilled before every `stream_value` call, so a plugin stuck in an
+// infinite loop traps on fuel exhaustion instead of hanging the sampling
+// thread forever. Generous enough that no well-behaved plugin should ever
+// hit it for a single sample.
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+// A stream backed by a sandboxed WebAssembly module. The module must export
+// stream_name/stream_unit/stream_min/stream_max/stream_value, and may
+// import hegemon::read_file to read an allowed /proc or /sys path (see
+// is_allowed_path) into its own linear memory.
+pub struct WasmStream {
+    store: Store<()>,
+    instance: Instance,
+    name: String,
+    unit: String,
+    min: Option<f64>,
+    max: Option<f64>,
+    value_fn: TypedFunc<(), f64>,
+}
+
+impl WasmStream {
+    // Instantiates path as a plugin; the caller skips it on error rather
+    // than aborting startup.
+    pub fn load(engine: &Engine, path: &Path) -> anyhow::Result<Self> {
+        let module = Module::from_file(engine, path)?;
+
+        let mut linker = Linker::new(engine);
+        linker.func_wrap(
+            "hegemon",
+            "read_file",
+            |mut caller: Caller<'_, ()>, path_ptr: i32, path_len: i32, buf_ptr: i32, buf_len: i32| -> i32 {
+                read_file_into_guest(&mut caller, path_ptr, path_len, buf_ptr, buf_len)
+            },
+        )?;
+
+        let mut store = Store::new(engine, ());
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+
+        let name = call_string_export(&mut store, &instance, "stream_name")?;
+        let unit = call_string_export(&mut store, &instance, "stream_unit")?;
+        let min = call_optional_f64_export(&mut store, &instance, "stream_min");
+        let max = call_optional_f64_export(&mut store, &instance, "stream_max");
+        let value_fn = instance.get_typed_func::<(), f64>(&mut store, "stream_value")?;
+
+        Ok(WasmStream {
+            store,
+            instance,
+            name,
+            unit,
+            min,
+            max,
+            value_fn,
+        })
+    }
+}
+
+impl Stream for WasmStream {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn unit(&self) -> String {
+        self.unit.clone()
+    }
+
+    fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    fn value(&mut self) -> Option<f64> {
+        // A trap, or a non-finite/out-of-range value, degrades to a
+        // missing sample instead of reaching update_streams's asserts
+        self.store.set_fuel(FUEL_PER_CALL).ok()?;
+        let value = self.value_fn.call(&mut self.store, ()).ok()?;
+
+        if !value.is_finite() {
+            return None;
+        }
+        if self.min.is_some_and(|min| value < min) {
+            return None;
+        }
+        if self.max.is_some_and(|max| value > max) {
+            return None;
+        }
+
+        Some(value)
+    }
+}
+
+fn call_string_export(store: &mut Store<()>, instance: &Instance, name: &str) -> anyhow::Result<String> {
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| anyhow::anyhow!("plugin does not export linear memory"))?;
+    let func = instance.get_typed_func::<(), (i32, i32)>(&mut *store, name)?;
+    let (ptr, len) = func.call(&mut *store, ())?;
+    read_string(&memory, store, ptr, len)
+}
+
+fn call_optional_f64_export(store: &mut Store<()>, instance: &Instance, name: &str) -> Option<f64> {
+    let func = instance.get_typed_func::<(), f64>(&mut *store, name).ok()?;
+    func.call(&mut *store, ()).ok().filter(|v| v.is_finite())
+}
+
+// Exported strings are just a stream's name/unit; a few hundred bytes is generous
+const MAX_EXPORT_STRING_LEN: usize = 256;
+
+fn read_string(memory: &Memory, store: &mut Store<()>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    let len = bounded_len(len, MAX_EXPORT_STRING_LEN)
+        .ok_or_else(|| anyhow::anyhow!("exported string length {} out of bounds", len))?;
+    let mut buf = vec![0u8; len];
+    memory.read(&mut *store, ptr as usize, &mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+// Rejects negative lengths (which would wrap to a huge usize) and anything above max
+fn bounded_len(len: i32, max: usize) -> Option<usize> {
+    let len = usize::try_from(len).ok()?;
+    if len > max {
+        return None;
+    }
+    Some(len)
+}
+
+// A real /proc or /sys stat path is well under this
+const MAX_PATH_LEN: usize = 4096;
+
+// Host-side import letting a plugin read an allowed path into its own memory.
+// Returns the number of bytes written, or -1 on error or if the path is not allowed.
+fn read_file_into_guest(
+    caller: &mut Caller<'_, ()>,
+    path_ptr: i32,
+    path_len: i32,
+    buf_ptr: i32,
+    buf_len: i32,
+) -> i32 {
+    let memory = match caller.get_export("memory").and_then(|e| e.into_memory()) {
+        Some(memory) => memory,
+        None => return -1,
+    };
+
+    let path_len = match bounded_len(path_len, MAX_PATH_LEN) {
+        Some(path_len) => path_len,
+        None => return -1,
+    };
+    let mut path_bytes = vec![0u8; path_len];
+    if memory.read(&mut *caller, path_ptr as usize, &mut path_bytes).is_err() {
+        return -1;
+    }
+    let path = match std::str::from_utf8(&path_bytes) {
+        Ok(path) => path,
+        Err(_) => return -1,
+    };
+
+    if !is_allowed_path(path) {
+        return -1;
+    }
+
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(_) => return -1,
+    };
+
+    let write_len = contents.len().min(buf_len as usize);
+    if memory.write(&mut *caller, buf_ptr as usize, &contents[..write_len]).is_err() {
+        return -1;
+    }
+
+    write_len as i32
+}
+
+// Global stat files a plugin may read whole. Deliberately excludes anything
+// under /proc/<pid> (environ, mem, maps, fd/*), which a system-stats plugin
+// doesn't need and which would otherwise expose other processes' secrets.
+const ALLOWED_READ_FILES: [&str; 7] = [
+    "/proc/stat",
+    "/proc/meminfo",
+    "/proc/loadavg",
+    "/proc/uptime",
+    "/proc/vmstat",
+    "/proc/diskstats",
+    "/proc/net/dev",
+];
+
+// /sys/class leaf directories exposing individual sensor readings
+const ALLOWED_READ_PREFIXES: [&str; 3] = [
+    "/sys/class/hwmon",
+    "/sys/class/thermal",
+    "/sys/class/power_supply",
+];
+
+// Resolves `.`/`..` components lexically (the target may not exist, or may
+// itself be a symlink) before checking against the allowlists above
+fn is_allowed_path(path: &str) -> bool {
+    use std::path::Component;
+
+    let mut normalized = std::path::PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+
+    ALLOWED_READ_FILES.iter().any(|file| normalized == Path::new(file))
+        || ALLOWED_READ_PREFIXES.iter().any(|prefix| normalized.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_allowed_path_accepts_the_documented_stat_files() {
+        assert!(is_allowed_path("/proc/stat"));
+        assert!(is_allowed_path("/proc/meminfo"));
+        assert!(is_allowed_path("/sys/class/hwmon/hwmon0/temp1_input"));
+    }
+
+    #[test]
+    fn is_allowed_path_rejects_other_proc_and_sys_paths() {
+        // The whole point of narrowing the allowlist: a plugin must not be
+        // able to read another process's environment or memory, or browse
+        // arbitrary /sys subtrees.
+        assert!(!is_allowed_path("/proc/self/environ"));
+        assert!(!is_allowed_path("/proc/1/environ"));
+        assert!(!is_allowed_path("/proc/1/mem"));
+        assert!(!is_allowed_path("/proc/1/fd/0"));
+        assert!(!is_allowed_path("/sys/firmware/efi/efivars"));
+    }
+
+    #[test]
+    fn is_allowed_path_rejects_paths_outside_proc_and_sys() {
+        assert!(!is_allowed_path("/etc/passwd"));
+        assert!(!is_allowed_path("/home/user/.ssh/id_rsa"));
+    }
+
+    #[test]
+    fn is_allowed_path_resolves_traversal_before_checking() {
+        assert!(!is_allowed_path("/proc/stat/../../etc/passwd"));
+        assert!(is_allowed_path("/proc/../proc/stat"));
+    }
+
+    #[test]
+    fn bounded_len_rejects_negative_and_oversized_lengths() {
+        assert_eq!(bounded_len(-1, 4096), None);
+        assert_eq!(bounded_len(i32::MIN, 4096), None);
+        assert_eq!(bounded_len(4097, 4096), None);
+        assert_eq!(bounded_len(4096, 4096), Some(4096));
+        assert_eq!(bounded_len(0, 4096), Some(0));
+    }
+}
+
+// Scans dir for .wasm modules; a plugin that fails to load is skipped
+pub fn load_plugins(dir: &Path) -> Vec<Box<dyn Stream>> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = match Engine::new(&config) {
+        Ok(engine) => engine,
+        Err(error) => {
+            eprintln!("Failed to initialize plugin engine: {}", error);
+            return Vec::new();
+        }
+    };
+    let mut streams: Vec<Box<dyn Stream>> = Vec::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return streams,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("wasm") {
+            continue;
+        }
+        match WasmStream::load(&engine, &path) {
+            Ok(stream) => streams.push(Box::new(stream)),
+            Err(error) => eprintln!("Failed to load plugin {}: {}", path.display(), error),
+        }
+    }
+
+    streams
+}